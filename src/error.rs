@@ -8,6 +8,15 @@ pub enum Error {
     #[error("Key ID in JWT header is not in the list of acceptable keys")]
     StrangeKid, // Heh :)
 
+    #[error("JWT header algorithm does not match the algorithm published for its key ID")]
+    AlgorithmMismatch,
+
+    #[error("Token's `sub` claim is not in the list of authorized subjects")]
+    UnauthorizedSubject,
+
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
 
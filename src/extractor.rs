@@ -0,0 +1,89 @@
+//! An `axum` extractor for validated token claims, gated behind the `axum`
+//! feature. Pulls `AzureAd` out of application state (via `FromRef`, the way
+//! axum-jwks' `KeyManager` does) so handlers can just declare `Claims<C>` as
+//! an argument instead of manually fishing the bearer token out and calling
+//! `validate_access_token` themselves.
+
+use std::fmt::Debug;
+
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    RequestPartsExt,
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{AzureAd, ValidationResult};
+
+/// Extracts and validates a `Bearer` access token from the `Authorization`
+/// header, deserializing its claims into `C`.
+#[derive(Debug, Clone)]
+pub struct Claims<C>(pub C);
+
+/// Why a `Claims<C>` extraction failed. Implements `IntoResponse` with
+/// sensible defaults; wrap `AzureAd` in your own extractor if you need a
+/// different response shape.
+#[derive(Debug)]
+pub enum ClaimsRejection {
+    /// No `Authorization: Bearer ...` header was present.
+    MissingToken,
+
+    /// The token failed signature/claim validation.
+    Invalid(crate::error::Error),
+
+    /// The token's `kid` isn't in our key set yet; the caller should retry
+    /// once keys have been refreshed.
+    NeedKeyRefresh,
+}
+
+impl IntoResponse for ClaimsRejection {
+    fn into_response(self) -> Response {
+        match self {
+            ClaimsRejection::MissingToken => {
+                (StatusCode::UNAUTHORIZED, "missing bearer token").into_response()
+            }
+            ClaimsRejection::Invalid(err) => {
+                (StatusCode::UNAUTHORIZED, err.to_string()).into_response()
+            }
+            ClaimsRejection::NeedKeyRefresh => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "signing keys need a refresh",
+            )
+                .into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<C, S> FromRequestParts<S> for Claims<C>
+where
+    C: DeserializeOwned + Debug,
+    AzureAd: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ClaimsRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let TypedHeader(Authorization(bearer)) = parts
+            .extract::<TypedHeader<Authorization<Bearer>>>()
+            .await
+            .map_err(|_| ClaimsRejection::MissingToken)?;
+
+        let azure_ad = AzureAd::from_ref(state);
+
+        match azure_ad
+            .validate_access_token::<C>(bearer.token())
+            .await
+            .map_err(ClaimsRejection::Invalid)?
+        {
+            ValidationResult::Valid(claims) => Ok(Claims(claims)),
+            ValidationResult::NeedKeyRefresh => Err(ClaimsRejection::NeedKeyRefresh),
+        }
+    }
+}
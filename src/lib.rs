@@ -1,16 +1,62 @@
 #![warn(missing_debug_implementations, unsafe_code)]
 #![deny(rust_2018_idioms, warnings)]
 
-use std::{collections::HashMap, fmt::Debug, time::Instant};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::Arc,
+    time::Duration,
+};
 
 use error::Error;
-use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm},
+    Algorithm, DecodingKey, Validation,
+};
 use serde::{de::DeserializeOwned, Deserialize};
+use tokio::{sync::RwLock, task::JoinHandle};
 
 pub mod error;
 
-/// How often should we refresh validation keys from Azure AD B2C?
-const KEYS_REFRESH_FREQUENCY_SECONDS: u64 = 60 * 60 * 8;
+#[cfg(feature = "axum")]
+pub mod extractor;
+
+/// Fallback interval used by `Strategy::Automatic` when the JWKS endpoint's
+/// response doesn't carry a `Cache-Control: max-age` directive we can parse.
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60 * 8);
+
+/// Floor applied to a `max-age`-derived refresh interval, mirroring
+/// oidc-jwt-validator's clamp, so a `max-age=0` (or other tiny value) can't
+/// turn `Strategy::Automatic` into a refresh storm against Azure AD B2C.
+pub const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 5);
+
+/// How long the background refresh task waits before retrying after a
+/// transient fetch failure under `Strategy::Automatic`, which has no
+/// configured interval of its own to fall back on.
+const RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Controls how often the background refresh task re-fetches the JWKS.
+#[derive(Debug, Clone, Copy)]
+pub enum Strategy {
+    /// Follow the `Cache-Control: max-age` directive on the JWKS response,
+    /// falling back to `DEFAULT_REFRESH_INTERVAL` when it's absent or not a
+    /// valid `max-age=N`.
+    Automatic,
+
+    /// Always wait a fixed duration between refreshes, ignoring whatever
+    /// caching hints the server sends.
+    Manual(Duration),
+}
+
+/// A decoding key together with the algorithm it's meant to verify, so we
+/// can reject a token whose header `alg` doesn't match what the key was
+/// published for.
+type KeyMap = HashMap<String, (DecodingKey, Algorithm)>;
+
+/// What `refresh_keys` resolves to for a single policy: its issuer (if the
+/// OIDC metadata carried one), its keys, and the JWKS response's `max-age`.
+type RefreshKeysResult = Result<(Option<String>, KeyMap, Option<Duration>), Error>;
 
 #[derive(Debug)]
 pub enum ValidationResult<T> {
@@ -27,95 +73,402 @@ impl<T> ValidationResult<T> {
     }
 }
 
+/// Owns the background key refresh task, if one was started. Aborts the
+/// task once the last `AzureAd` clone referencing it is dropped.
+struct RefreshHandle(JoinHandle<()>);
+
+impl Drop for RefreshHandle {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl Debug for RefreshHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RefreshHandle").finish()
+    }
+}
+
+/// One configured B2C user flow: its name, the issuer its tokens carry
+/// (once known), and the keys published on its JWKS.
+struct Policy {
+    policy_name: String,
+    issuer: Option<String>,
+    keys: KeyMap,
+}
+
+impl Debug for Policy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Policy")
+            .field("policy_name", &self.policy_name)
+            .field("issuer", &self.issuer)
+            .field("keys", &self.keys.keys())
+            .finish()
+    }
+}
+
 #[derive(Clone)]
 pub struct AzureAd {
     tenant_name: String,
-    policy_name: String,
-    keys: HashMap<String, DecodingKey>,
-    last_key_refresh_time: Instant,
+    policy_names: Vec<String>,
+    policies: Arc<RwLock<Vec<Policy>>>,
     validation: Validation,
+    authorized_subjects: Option<Vec<String>>,
+    refresh_handle: Option<Arc<RefreshHandle>>,
 }
 
 impl Debug for AzureAd {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AzureAd")
             .field("tenant_name", &self.tenant_name)
-            .field("policy_name", &self.policy_name)
-            .field("keys", &self.keys.keys())
-            .field("last_key_refresh_time", &self.last_key_refresh_time)
+            .field("policy_names", &self.policy_names)
             .field("validation", &self.validation)
+            .field("authorized_subjects", &self.authorized_subjects)
+            .field("auto_refresh", &self.refresh_handle.is_some())
             .finish()
     }
 }
 
 impl AzureAd {
+    /// Creates a new validator for the given tenant/policy with the default
+    /// validation settings (zero leeway, the standard required claims, no
+    /// subject allowlist). Use `AzureAdBuilder` to customize those.
+    ///
+    /// When `strategy` is `Some`, a background task is spawned that
+    /// re-fetches the JWKS and swaps the keys in behind a lock, so
+    /// `validate_access_token` rarely has to report
+    /// `ValidationResult::NeedKeyRefresh`. The cadence follows the chosen
+    /// `Strategy`. The task keeps running as long as at least one clone of
+    /// the returned `AzureAd` is alive, and is cancelled once the last one is
+    /// dropped. Pass `None` to manage refreshing yourself via
+    /// `refresh_validation_keys`.
     pub async fn new(
         tenant_name: String,
         policy_name: String,
         app_ids: Option<Vec<String>>,
+        strategy: Option<Strategy>,
     ) -> Result<Self, Error> {
-        // initalize list of acceptable keys
-        let (issuer, keys) = refresh_keys(&tenant_name, &policy_name).await?;
-
-        // initialize validation params
-        let mut validation = Validation::new(Algorithm::RS256);
-        validation.set_required_spec_claims(&["iss", "sub", "exp", "nbf", "aud"]);
-        validation.validate_exp = true;
-        validation.validate_nbf = true;
+        let mut builder = AzureAdBuilder::new(tenant_name, policy_name);
         if let Some(app_ids) = app_ids {
-            validation.set_audience(&app_ids);
+            builder = builder.app_ids(app_ids);
         }
-        if let Some(issuer) = issuer {
-            validation.set_issuer(&[issuer]);
+        if let Some(strategy) = strategy {
+            builder = builder.strategy(strategy);
         }
 
-        Ok(Self {
-            tenant_name,
-            policy_name,
-            keys,
-            validation,
-            last_key_refresh_time: Instant::now(),
-        })
+        builder.build().await
     }
 
+    /// Immediately re-fetches validation keys for every configured policy
+    /// from Azure AD B2C. Useful for a one-off refresh, or when `AzureAd`
+    /// was created without a `Strategy`.
     pub async fn refresh_validation_keys(&mut self) -> Result<(), Error> {
-        // if we tried refreshing keys too recently then fail
-        if self.last_key_refresh_time.elapsed().as_secs() < KEYS_REFRESH_FREQUENCY_SECONDS {
-            return Err(Error::StrangeKid);
-        }
-
-        let (issuer, keys) = refresh_keys(&self.tenant_name, &self.policy_name).await?;
-        self.keys = keys;
-        if let Some(issuer) = issuer {
-            self.validation.set_issuer(&[issuer]);
-        }
-        self.last_key_refresh_time = Instant::now();
+        let (policies, _) = refresh_all_policies(&self.tenant_name, &self.policy_names).await?;
+        *self.policies.write().await = policies;
 
         Ok(())
     }
 
-    pub fn validate_access_token<T: DeserializeOwned + Debug>(
+    pub async fn validate_access_token<T: DeserializeOwned + Debug>(
         &self,
         access_token: &str,
     ) -> Result<ValidationResult<T>, Error> {
-        // decode header and locate the public key from oid metadata
+        // decode header and locate the public key from whichever configured
+        // policy's JWKS published this kid
         let header = decode_header(access_token)?;
         let key_id = header.kid.ok_or(Error::MissingKid)?;
 
-        Ok(self
-            .keys
-            .get(&key_id)
-            .map(|key| decode(access_token, key, &self.validation))
-            .transpose()?
-            .map(|v| ValidationResult::Valid(v.claims))
-            .unwrap_or(ValidationResult::NeedKeyRefresh))
+        let policies = self.policies.read().await;
+        let matched = policies
+            .iter()
+            .find_map(|policy| policy.keys.get(&key_id).map(|key| (policy, key)));
+
+        let Some((policy, (key, algorithm))) = matched else {
+            return Ok(ValidationResult::NeedKeyRefresh);
+        };
+
+        if *algorithm != header.alg {
+            return Err(Error::AlgorithmMismatch);
+        }
+
+        let mut validation = self.validation.clone();
+        validation.algorithms = vec![*algorithm];
+        if let Some(issuer) = &policy.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+
+        // decode into a generic JSON value first so we can check `sub`
+        // against the authorized subjects allowlist before handing the
+        // caller a typed `T`
+        let claims = decode::<serde_json::Value>(access_token, key, &validation)?.claims;
+
+        if let Some(authorized_subjects) = &self.authorized_subjects {
+            let sub = claims.get("sub").and_then(serde_json::Value::as_str);
+            if !sub.is_some_and(|sub| authorized_subjects.iter().any(|s| s == sub)) {
+                return Err(Error::UnauthorizedSubject);
+            }
+        }
+
+        Ok(ValidationResult::Valid(serde_json::from_value(claims)?))
     }
 }
 
-async fn refresh_keys(
+/// Builds an `AzureAd` with validation settings beyond `AzureAd::new`'s
+/// defaults: clock skew `leeway`, a custom required-claims list, toggling
+/// `exp`/`nbf` checks, an allowlist of `sub` values a token must carry, and
+/// extra B2C policies to accept tokens from.
+#[derive(Debug, Clone)]
+pub struct AzureAdBuilder {
+    tenant_name: String,
+    policy_names: Vec<String>,
+    app_ids: Option<Vec<String>>,
+    strategy: Option<Strategy>,
+    leeway: u64,
+    required_claims: Vec<String>,
+    validate_exp: bool,
+    validate_nbf: bool,
+    authorized_subjects: Option<Vec<String>>,
+}
+
+impl AzureAdBuilder {
+    pub fn new(tenant_name: impl Into<String>, policy_name: impl Into<String>) -> Self {
+        Self {
+            tenant_name: tenant_name.into(),
+            policy_names: vec![policy_name.into()],
+            app_ids: None,
+            strategy: None,
+            leeway: 0,
+            required_claims: ["iss", "sub", "exp", "nbf", "aud"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            validate_exp: true,
+            validate_nbf: true,
+            authorized_subjects: None,
+        }
+    }
+
+    /// Adds another B2C user flow (e.g. password-reset or profile-edit
+    /// alongside a primary sign-up/sign-in policy) to accept tokens from.
+    /// Each policy gets its own JWKS fetch and issuer; a token validates if
+    /// any configured policy's keys and issuer match.
+    pub fn policy(mut self, policy_name: impl Into<String>) -> Self {
+        self.policy_names.push(policy_name.into());
+        self
+    }
+
+    /// Restricts accepted tokens to the given `aud` values.
+    pub fn app_ids(mut self, app_ids: Vec<String>) -> Self {
+        self.app_ids = Some(app_ids);
+        self
+    }
+
+    /// Starts a background key refresh task following `strategy`. See
+    /// `AzureAd::new` for details.
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = Some(strategy);
+        self
+    }
+
+    /// Seconds of clock skew tolerance applied to `exp`/`nbf` checks.
+    pub fn leeway(mut self, leeway: u64) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Overrides the default required-claims list (`iss`, `sub`, `exp`,
+    /// `nbf`, `aud`).
+    pub fn required_claims<I, S>(mut self, required_claims: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.required_claims = required_claims.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Toggles `exp` validation. Enabled by default.
+    pub fn validate_exp(mut self, validate_exp: bool) -> Self {
+        self.validate_exp = validate_exp;
+        self
+    }
+
+    /// Toggles `nbf` validation. Enabled by default.
+    pub fn validate_nbf(mut self, validate_nbf: bool) -> Self {
+        self.validate_nbf = validate_nbf;
+        self
+    }
+
+    /// Restricts accepted tokens to the given `sub` values, checked after
+    /// signature verification. A token with a `sub` outside this list fails
+    /// with `Error::UnauthorizedSubject`.
+    pub fn authorized_subjects(mut self, authorized_subjects: Vec<String>) -> Self {
+        self.authorized_subjects = Some(authorized_subjects);
+        self
+    }
+
+    pub async fn build(self) -> Result<AzureAd, Error> {
+        // initialize list of acceptable keys for every configured policy
+        let (policies, max_age) =
+            refresh_all_policies(&self.tenant_name, &self.policy_names).await?;
+        let policies = Arc::new(RwLock::new(policies));
+
+        // initialize validation params; the algorithm is a placeholder that
+        // validate_access_token swaps out per-token to match whatever
+        // key/alg the `kid` actually resolves to, and the issuer is left
+        // unset here since it's resolved per-policy at validation time
+        let mut validation = Validation::new(Algorithm::RS256);
+        let required_claims: Vec<&str> =
+            self.required_claims.iter().map(String::as_str).collect();
+        validation.set_required_spec_claims(&required_claims);
+        validation.validate_exp = self.validate_exp;
+        validation.validate_nbf = self.validate_nbf;
+        validation.leeway = self.leeway;
+        if let Some(app_ids) = self.app_ids {
+            validation.set_audience(&app_ids);
+        }
+
+        let refresh_handle = self.strategy.map(|strategy| {
+            Arc::new(RefreshHandle(spawn_refresh_task(
+                self.tenant_name.clone(),
+                self.policy_names.clone(),
+                Arc::clone(&policies),
+                strategy,
+                next_refresh_wait(strategy, max_age),
+            )))
+        });
+
+        Ok(AzureAd {
+            tenant_name: self.tenant_name,
+            policy_names: self.policy_names,
+            policies,
+            validation,
+            authorized_subjects: self.authorized_subjects,
+            refresh_handle,
+        })
+    }
+}
+
+/// Works out how long to wait before the next refresh given the chosen
+/// `Strategy` and the `max-age` (if any) parsed off the last JWKS response.
+fn next_refresh_wait(strategy: Strategy, max_age: Option<Duration>) -> Duration {
+    match strategy {
+        Strategy::Manual(interval) => interval,
+        Strategy::Automatic => max_age.unwrap_or(DEFAULT_REFRESH_INTERVAL),
+    }
+}
+
+/// Spawns the background task that keeps `policies` fresh, waiting
+/// `initial_wait` before the first refresh and then following `strategy`
+/// thereafter.
+fn spawn_refresh_task(
+    tenant_name: String,
+    policy_names: Vec<String>,
+    policies: Arc<RwLock<Vec<Policy>>>,
+    strategy: Strategy,
+    initial_wait: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut wait = initial_wait;
+
+        loop {
+            tokio::time::sleep(wait).await;
+
+            match refresh_all_policies(&tenant_name, &policy_names).await {
+                Ok((new_policies, max_age)) => {
+                    *policies.write().await = new_policies;
+                    wait = next_refresh_wait(strategy, max_age);
+                }
+                Err(_) => {
+                    // transient failure: keep serving the keys we already
+                    // have. Back off on the configured strategy's own
+                    // cadence rather than the unrelated 8h default, so a
+                    // `Manual(short_interval)` deployment keeps retrying at
+                    // roughly the rate it asked for; `Automatic` has no
+                    // interval of its own yet, so fall back to a short retry
+                    // instead of waiting a full `DEFAULT_REFRESH_INTERVAL`.
+                    wait = match strategy {
+                        Strategy::Manual(interval) => interval,
+                        Strategy::Automatic => RETRY_BACKOFF,
+                    };
+                }
+            }
+        }
+    })
+}
+
+/// Parses the `max-age=N` directive out of a `Cache-Control` response
+/// header, if present and well-formed.
+fn parse_max_age(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::CACHE_CONTROL)?
+        .to_str()
+        .ok()?
+        .split(',')
+        .map(str::trim)
+        .find_map(|directive| directive.strip_prefix("max-age="))
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .map(|max_age| max_age.max(MIN_REFRESH_INTERVAL))
+}
+
+/// Re-fetches every configured policy's JWKS, returning the new `Policy`
+/// list and the soonest `max-age` seen across all of them (so under
+/// `Strategy::Automatic` no policy's keys are kept past its own cache hint,
+/// except where that hint is below the `MIN_REFRESH_INTERVAL` floor). See
+/// `fold_policy_refreshes` for how a single policy's fetch failure is
+/// handled.
+async fn refresh_all_policies(
     tenant_name: &str,
-    policy_name: &str,
-) -> Result<(Option<String>, HashMap<String, DecodingKey>), Error> {
+    policy_names: &[String],
+) -> Result<(Vec<Policy>, Option<Duration>), Error> {
+    let mut results = Vec::with_capacity(policy_names.len());
+
+    for policy_name in policy_names {
+        let result = refresh_keys(tenant_name, policy_name).await;
+        results.push((policy_name.clone(), result));
+    }
+
+    fold_policy_refreshes(results)
+}
+
+/// Folds the per-policy refresh results into a `Policy` list and the
+/// soonest `max-age` seen, skipping (rather than failing on) any policy
+/// whose fetch errored; only returns `Err` once every policy has failed,
+/// since at that point there are no keys left to validate against.
+fn fold_policy_refreshes(
+    results: Vec<(String, RefreshKeysResult)>,
+) -> Result<(Vec<Policy>, Option<Duration>), Error> {
+    let mut policies = Vec::with_capacity(results.len());
+    let mut min_max_age = None;
+    let mut last_err = None;
+
+    for (policy_name, result) in results {
+        match result {
+            Ok((issuer, keys, max_age)) => {
+                min_max_age = match (min_max_age, max_age) {
+                    (None, max_age) => max_age,
+                    (Some(a), None) => Some(a),
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                };
+                policies.push(Policy {
+                    policy_name,
+                    issuer,
+                    keys,
+                });
+            }
+            Err(err) => last_err = Some(err),
+        }
+    }
+
+    if policies.is_empty() {
+        return Err(last_err.unwrap_or(Error::Unknown));
+    }
+
+    Ok((policies, min_max_age))
+}
+
+async fn refresh_keys(tenant_name: &str, policy_name: &str) -> RefreshKeysResult {
     // fetch oid metadata
     let metadata_uri = format!(
         "https://{}.b2clogin.com/{}.onmicrosoft.com/{}/v2.0/.well-known/openid-configuration",
@@ -126,45 +479,132 @@ async fn refresh_keys(
         .json::<OidMetadata>()
         .await?;
 
-    let keys_metadata = reqwest::get(&oid_metadata.jwks_uri)
-        .await?
-        .json::<KeysMetadata>()
-        .await?;
+    let jwks_response = reqwest::get(&oid_metadata.jwks_uri).await?;
+    let max_age = parse_max_age(jwks_response.headers());
+    let jwk_set = jwks_response.json::<JwkSet>().await?;
 
-    Ok((
-        oid_metadata.issuer,
-        keys_metadata
-            .keys
-            .into_iter()
-            .map(|key| {
-                Ok((
-                    key.key_id,
-                    DecodingKey::from_rsa_components(&key.rsa_modulus, &key.rsa_exponent)?,
-                ))
-            })
-            .collect::<Result<_, Error>>()?,
-    ))
+    // keys we can't identify (no kid) or whose algorithm we can't work out
+    // are skipped rather than failing the whole refresh, since B2C JWKS
+    // endpoints are free to publish key types we don't understand yet
+    let keys = jwk_set
+        .keys
+        .into_iter()
+        .filter_map(|jwk| {
+            let key_id = jwk.common.key_id.clone()?;
+            let algorithm = jwk_algorithm(&jwk)?;
+            let key = DecodingKey::from_jwk(&jwk).ok()?;
+
+            Some((key_id, (key, algorithm)))
+        })
+        .collect();
+
+    Ok((oid_metadata.issuer, keys, max_age))
 }
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct OidMetadata {
-    issuer: Option<String>,
-    jwks_uri: String,
+/// Works out the `jsonwebtoken::Algorithm` a JWK is meant to be used with,
+/// preferring the explicit `alg` field and falling back to inferring it from
+/// the key type (and, for EC keys, the curve) when `alg` is absent, which is
+/// common on Azure AD B2C's JWKS.
+fn jwk_algorithm(jwk: &Jwk) -> Option<Algorithm> {
+    if let Some(key_algorithm) = jwk.common.key_algorithm {
+        return key_algorithm_to_algorithm(key_algorithm);
+    }
+
+    match &jwk.algorithm {
+        AlgorithmParameters::RSA(_) => Some(Algorithm::RS256),
+        AlgorithmParameters::EllipticCurve(params) => match params.curve {
+            EllipticCurve::P256 => Some(Algorithm::ES256),
+            EllipticCurve::P384 => Some(Algorithm::ES384),
+            // P521 isn't supported by jsonwebtoken's signing algorithms, and
+            // Ed25519 keys are represented as AlgorithmParameters::OctetKeyPair
+            // rather than EllipticCurve, so this shouldn't occur in practice
+            EllipticCurve::P521 | EllipticCurve::Ed25519 => None,
+        },
+        AlgorithmParameters::OctetKeyPair(_) | AlgorithmParameters::OctetKey(_) => None,
+    }
 }
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct KeysMetadata {
-    keys: Vec<KeyMetadata>,
+fn key_algorithm_to_algorithm(key_algorithm: KeyAlgorithm) -> Option<Algorithm> {
+    match key_algorithm {
+        KeyAlgorithm::HS256 => Some(Algorithm::HS256),
+        KeyAlgorithm::HS384 => Some(Algorithm::HS384),
+        KeyAlgorithm::HS512 => Some(Algorithm::HS512),
+        KeyAlgorithm::RS256 => Some(Algorithm::RS256),
+        KeyAlgorithm::RS384 => Some(Algorithm::RS384),
+        KeyAlgorithm::RS512 => Some(Algorithm::RS512),
+        KeyAlgorithm::ES256 => Some(Algorithm::ES256),
+        KeyAlgorithm::ES384 => Some(Algorithm::ES384),
+        KeyAlgorithm::PS256 => Some(Algorithm::PS256),
+        KeyAlgorithm::PS384 => Some(Algorithm::PS384),
+        KeyAlgorithm::PS512 => Some(Algorithm::PS512),
+        KeyAlgorithm::EdDSA => Some(Algorithm::EdDSA),
+        _ => None,
+    }
 }
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct KeyMetadata {
-    #[serde(rename = "kid")]
-    key_id: String,
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::jwk::{CommonParameters, EllipticCurveKeyParameters, EllipticCurveKeyType};
+
+    use super::*;
 
-    #[serde(rename = "n")]
-    rsa_modulus: String,
+    #[test]
+    fn jwk_algorithm_infers_es256_from_p256_curve_when_alg_is_absent() {
+        let jwk = Jwk {
+            common: CommonParameters::default(),
+            algorithm: AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+                key_type: EllipticCurveKeyType::EC,
+                curve: EllipticCurve::P256,
+                x: String::new(),
+                y: String::new(),
+            }),
+        };
 
-    #[serde(rename = "e")]
-    rsa_exponent: String,
+        assert_eq!(jwk_algorithm(&jwk), Some(Algorithm::ES256));
+    }
+
+    #[test]
+    fn parse_max_age_clamps_a_tiny_max_age_up_to_the_minimum() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            reqwest::header::CACHE_CONTROL,
+            "max-age=0".parse().unwrap(),
+        );
+
+        assert_eq!(parse_max_age(&headers), Some(MIN_REFRESH_INTERVAL));
+    }
+
+    #[test]
+    fn parse_max_age_is_none_without_a_usable_cache_control_header() {
+        assert_eq!(parse_max_age(&reqwest::header::HeaderMap::new()), None);
+
+        let mut garbage = reqwest::header::HeaderMap::new();
+        garbage.insert(
+            reqwest::header::CACHE_CONTROL,
+            "no-cache".parse().unwrap(),
+        );
+        assert_eq!(parse_max_age(&garbage), None);
+    }
+
+    #[test]
+    fn fold_policy_refreshes_keeps_surviving_policies_when_one_fetch_fails() {
+        let results = vec![
+            (
+                "policy-a".to_string(),
+                Ok((Some("issuer-a".to_string()), KeyMap::new(), None)),
+            ),
+            ("policy-b".to_string(), Err(Error::Unknown)),
+        ];
+
+        let (policies, _) = fold_policy_refreshes(results).expect("at least one policy survived");
+
+        assert_eq!(policies.len(), 1);
+        assert_eq!(policies[0].policy_name, "policy-a");
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct OidMetadata {
+    issuer: Option<String>,
+    jwks_uri: String,
 }